@@ -1,5 +1,6 @@
 //! Timers
 
+use crate::hal::blocking::delay::{DelayMs, DelayUs};
 use crate::hal::timer::{Cancel, CountDown, Periodic};
 use crate::pac::{
     TIM1, TIM10, TIM11, TIM12, TIM13, TIM14, TIM2, TIM3, TIM4, TIM5, TIM6, TIM7, TIM8, TIM9,
@@ -7,9 +8,29 @@ use crate::pac::{
 use crate::rcc::{Clocks, APB1, APB2};
 use crate::time::Hertz;
 use cast::{u16, u32};
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use cortex_m::peripheral::syst::SystClkSource;
+use cortex_m::peripheral::SYST;
 use nb;
 use void::Void;
 
+/// Millisecond (or, more generally, tick) counter incremented by
+/// [`Timer::on_interrupt`]. Read it with [`millis`]/[`ticks`].
+static TICKS: Mutex<Cell<u32>> = Mutex::new(Cell::new(0));
+
+/// Returns the number of ticks counted so far by a timer started with
+/// [`Timer::tick_timer`]
+pub fn ticks() -> u32 {
+    cortex_m::interrupt::free(|cs| TICKS.borrow(cs).get())
+}
+
+/// Returns the number of milliseconds elapsed so far, assuming the timer
+/// started with [`Timer::tick_timer`] was configured for a 1 kHz tick rate
+pub fn millis() -> u32 {
+    ticks()
+}
+
 /// Hardware timers
 pub struct Timer<TIM> {
     clock: Hertz,
@@ -32,7 +53,7 @@ pub enum Error {
 }
 
 macro_rules! hal {
-    ($($TIM:ident: ($tim:ident, $timXen:ident, $timXrst:ident, $apb:ident, $timclk:ident),)+) => {
+    ($($TIM:ident: ($tim:ident, $timXen:ident, $timXrst:ident, $apb:ident, $timclk:ident, $max_arr:expr),)+) => {
         $(
             impl Periodic for Timer<$TIM> {}
 
@@ -114,6 +135,31 @@ macro_rules! hal {
                     timer
                 }
 
+                /// Configures a TIM peripheral as a periodic count down timer
+                /// that increments the global [`ticks`]/[`millis`] counter on
+                /// every timeout, via [`Timer::on_interrupt`]
+                pub fn tick_timer<T>(tim: $TIM, tick_hz: T, clocks: Clocks, apb: &mut $apb) -> Self
+                where
+                    T: Into<Hertz>,
+                {
+                    let mut timer = Self::$tim(tim, tick_hz, clocks, apb);
+                    timer.listen(Event::TimeOut);
+
+                    timer
+                }
+
+                /// Services the update interrupt for a timer started with
+                /// [`Timer::tick_timer`], clearing the flag and incrementing
+                /// the global [`ticks`]/[`millis`] counter
+                pub fn on_interrupt(&mut self) {
+                    self.clear_interrupt(Event::TimeOut);
+
+                    cortex_m::interrupt::free(|cs| {
+                        let ticks = TICKS.borrow(cs);
+                        ticks.set(ticks.get() + 1);
+                    });
+                }
+
                 /// Starts listening for an `event`
                 pub fn listen(&mut self, event: Event) {
                     match event {
@@ -154,6 +200,44 @@ macro_rules! hal {
                     self.tim
                 }
 
+                /// Configures a TIM peripheral as a free-running up-counter
+                /// ticking at `resolution`, instead of a periodic count down
+                pub fn start_count_up<T>(tim: $TIM, resolution: T, clocks: Clocks, apb: &mut $apb) -> Self
+                where
+                    T: Into<Hertz>,
+                {
+                    apb.enr().modify(|_, w| w.$timXen().set_bit());
+                    apb.rstr().modify(|_, w| w.$timXrst().set_bit());
+                    apb.rstr().modify(|_, w| w.$timXrst().clear_bit());
+
+                    let clock = clocks.$timclk();
+                    let resolution = resolution.into();
+                    let psc = u16(clock.0 / resolution.0 - 1).unwrap();
+                    tim.psc.write(|w| unsafe { w.psc().bits(psc) });
+                    tim.arr.write(|w| unsafe { w.bits($max_arr) });
+
+                    tim.egr.write(|w| w.ug().set_bit());
+                    tim.sr.modify(|_, w| w.uif().clear_bit());
+
+                    tim.cr1.modify(|_, w| w.cen().set_bit());
+
+                    Timer {
+                        clock,
+                        tim,
+                        timeout: Hertz(0),
+                    }
+                }
+
+                /// Returns the current value of the counter (`CNT`)
+                pub fn counter(&self) -> u32 {
+                    self.tim.cnt.read().bits()
+                }
+
+                /// Resets the counter (`CNT`) to zero
+                pub fn reset(&mut self) {
+                    self.tim.cnt.reset();
+                }
+
                 /// Enables the counter.
                 fn enable(&mut self) {
                     self.tim.cr1.modify(|_, w| w.cen().set_bit());
@@ -163,27 +247,175 @@ macro_rules! hal {
                 fn disable(&mut self) {
                     self.tim.cr1.modify(|_, w| w.cen().clear_bit());
                 }
+
+                /// Busy-waits for `ticks` timer ticks, one-shot, splitting the
+                /// delay into multiple chunks if it doesn't fit in the 16-bit ARR
+                fn delay_ticks(&mut self, mut ticks: u64) {
+                    const MAX_CHUNK: u64 = 0xFFFF;
+
+                    // Stop the counter after a single update event instead of
+                    // reloading it, so each chunk only fires once
+                    self.tim.cr1.modify(|_, w| w.opm().set_bit());
+
+                    while ticks > 0 {
+                        let chunk = if ticks > MAX_CHUNK { MAX_CHUNK } else { ticks };
+                        ticks -= chunk;
+                        let chunk = chunk as u32;
+
+                        self.tim.psc.write(|w| unsafe { w.psc().bits(0) });
+                        self.tim.arr.write(|w| unsafe { w.bits(chunk) });
+
+                        self.tim.egr.write(|w| w.ug().set_bit());
+                        self.tim.sr.modify(|_, w| w.uif().clear_bit());
+
+                        self.enable();
+
+                        while self.tim.sr.read().uif().bit_is_clear() {}
+                        self.tim.sr.modify(|_, w| w.uif().clear_bit());
+                    }
+
+                    self.tim.cr1.modify(|_, w| w.opm().clear_bit());
+                }
+            }
+
+            impl DelayUs<u32> for Timer<$TIM> {
+                fn delay_us(&mut self, us: u32) {
+                    let ticks = u64::from(self.clock.0) / 1_000_000 * u64::from(us);
+                    self.delay_ticks(ticks);
+                }
+            }
+
+            impl DelayUs<u16> for Timer<$TIM> {
+                fn delay_us(&mut self, us: u16) {
+                    self.delay_us(u32(us));
+                }
+            }
+
+            impl DelayUs<u8> for Timer<$TIM> {
+                fn delay_us(&mut self, us: u8) {
+                    self.delay_us(u32(us));
+                }
+            }
+
+            impl DelayMs<u32> for Timer<$TIM> {
+                fn delay_ms(&mut self, ms: u32) {
+                    let ticks = u64::from(self.clock.0) / 1_000 * u64::from(ms);
+                    self.delay_ticks(ticks);
+                }
+            }
+
+            impl DelayMs<u16> for Timer<$TIM> {
+                fn delay_ms(&mut self, ms: u16) {
+                    self.delay_ms(u32(ms));
+                }
+            }
+
+            impl DelayMs<u8> for Timer<$TIM> {
+                fn delay_ms(&mut self, ms: u8) {
+                    self.delay_ms(u32(ms));
+                }
             }
         )+
     }
 }
 
 hal! {
-    TIM2: (tim2, tim2en, tim2rst, APB1, timclk1),
-    TIM3: (tim3, tim3en, tim3rst, APB1, timclk1),
-    TIM4: (tim4, tim4en, tim4rst, APB1, timclk1),
-    TIM5: (tim5, tim5en, tim5rst, APB1, timclk1),
-    TIM6: (tim6, tim6en, tim6rst, APB1, timclk1),
-    TIM7: (tim7, tim7en, tim7rst, APB1, timclk1),
-    TIM12: (tim12, tim12en, tim12rst, APB1, timclk1),
-    TIM13: (tim13, tim13en, tim13rst, APB1, timclk1),
-    TIM14: (tim14, tim14en, tim14rst, APB1, timclk1),
-
-    TIM1: (tim1, tim1en, tim1rst, APB2, timclk2),
-    TIM8: (tim8, tim8en, tim8rst, APB2, timclk2),
-    TIM9: (tim9, tim9en, tim9rst, APB2, timclk2),
-    TIM10: (tim10, tim10en, tim10rst, APB2, timclk2),
-    TIM11: (tim11, tim11en, tim11rst, APB2, timclk2),
+    TIM2: (tim2, tim2en, tim2rst, APB1, timclk1, 0xFFFF_FFFF),
+    TIM3: (tim3, tim3en, tim3rst, APB1, timclk1, 0xFFFF),
+    TIM4: (tim4, tim4en, tim4rst, APB1, timclk1, 0xFFFF),
+    TIM5: (tim5, tim5en, tim5rst, APB1, timclk1, 0xFFFF_FFFF),
+    TIM6: (tim6, tim6en, tim6rst, APB1, timclk1, 0xFFFF),
+    TIM7: (tim7, tim7en, tim7rst, APB1, timclk1, 0xFFFF),
+    TIM12: (tim12, tim12en, tim12rst, APB1, timclk1, 0xFFFF),
+    TIM13: (tim13, tim13en, tim13rst, APB1, timclk1, 0xFFFF),
+    TIM14: (tim14, tim14en, tim14rst, APB1, timclk1, 0xFFFF),
+
+    TIM1: (tim1, tim1en, tim1rst, APB2, timclk2, 0xFFFF),
+    TIM8: (tim8, tim8en, tim8rst, APB2, timclk2, 0xFFFF),
+    TIM9: (tim9, tim9en, tim9rst, APB2, timclk2, 0xFFFF),
+    TIM10: (tim10, tim10en, tim10rst, APB2, timclk2, 0xFFFF),
+    TIM11: (tim11, tim11en, tim11rst, APB2, timclk2, 0xFFFF),
+}
+
+impl Periodic for Timer<SYST> {}
+
+impl CountDown for Timer<SYST> {
+    type Time = Hertz;
+
+    fn start<T>(&mut self, timeout: T)
+    where
+        T: Into<Hertz>,
+    {
+        self.tim.disable_counter();
+
+        self.timeout = timeout.into();
+        let reload = self.clock.0 / self.timeout.0 - 1;
+        assert!(reload < (1 << 24));
+
+        self.tim.set_reload(reload);
+        self.tim.clear_current();
+        self.tim.enable_counter();
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        if self.tim.has_wrapped() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl Cancel for Timer<SYST> {
+    type Error = Error;
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        if !self.tim.is_counter_enabled() {
+            return Err(Error::Disabled);
+        }
+
+        self.tim.disable_counter();
+
+        Ok(())
+    }
+}
+
+impl Timer<SYST> {
+    /// Configures the SysTick (SYST) timer as a periodic count down timer
+    pub fn syst<T>(mut syst: SYST, timeout: T, clocks: Clocks) -> Self
+    where
+        T: Into<Hertz>,
+    {
+        syst.set_clock_source(SystClkSource::Core);
+
+        let mut timer = Timer {
+            clock: clocks.sysclk(),
+            tim: syst,
+            timeout: Hertz(0),
+        };
+        timer.start(timeout);
+
+        timer
+    }
+
+    /// Starts listening for an `event`
+    pub fn listen(&mut self, event: Event) {
+        match event {
+            Event::TimeOut => self.tim.enable_interrupt(),
+        }
+    }
+
+    /// Stops listening for an `event`
+    pub fn unlisten(&mut self, event: Event) {
+        match event {
+            Event::TimeOut => self.tim.disable_interrupt(),
+        }
+    }
+
+    /// Releases the SYST
+    pub fn free(self) -> SYST {
+        self.tim
+    }
 }
 
 #[cfg(any(
@@ -421,3 +653,519 @@ channel_impl!(
     TIM11, PinC1, PB9, Alternate<AF3>;
     TIM11, PinC1, PF7, Alternate<AF3>;
 );
+
+/// PWM
+pub mod pwm {
+    use core::marker::PhantomData;
+
+    use cast::{u16, u32};
+
+    use crate::hal::PwmPin;
+    use crate::pac::{TIM1, TIM10, TIM11, TIM2, TIM3, TIM4, TIM5, TIM8, TIM9};
+    use crate::rcc::{Clocks, APB1, APB2};
+    use crate::time::Hertz;
+
+    use super::{PinC1, PinC2, PinC3, PinC4, Timer};
+
+    /// Channel 1
+    pub struct C1;
+    /// Channel 2
+    pub struct C2;
+    /// Channel 3
+    pub struct C3;
+    /// Channel 4
+    pub struct C4;
+
+    /// A single PWM channel generated by splitting up a `Timer`
+    pub struct Pwm<TIM, CHANNEL> {
+        _channel: PhantomData<CHANNEL>,
+        _tim: PhantomData<TIM>,
+    }
+
+    #[doc(hidden)]
+    pub trait Configure {
+        fn setup();
+    }
+
+    /// Pins that can be used to drive PWM channels on `TIM`
+    pub trait Pins<TIM> {
+        /// The bundle of configured PWM channels produced for these pins
+        type Channels;
+
+        #[doc(hidden)]
+        fn configure();
+        #[doc(hidden)]
+        fn channels() -> Self::Channels;
+    }
+
+    impl<TIM, P1> Pins<TIM> for (P1,)
+    where
+        P1: PinC1<TIM>,
+        Pwm<TIM, C1>: Configure,
+    {
+        type Channels = Pwm<TIM, C1>;
+
+        fn configure() {
+            Pwm::<TIM, C1>::setup();
+        }
+
+        fn channels() -> Self::Channels {
+            Pwm {
+                _channel: PhantomData,
+                _tim: PhantomData,
+            }
+        }
+    }
+
+    impl<TIM, P1, P2> Pins<TIM> for (P1, P2)
+    where
+        P1: PinC1<TIM>,
+        P2: PinC2<TIM>,
+        Pwm<TIM, C1>: Configure,
+        Pwm<TIM, C2>: Configure,
+    {
+        type Channels = (Pwm<TIM, C1>, Pwm<TIM, C2>);
+
+        fn configure() {
+            Pwm::<TIM, C1>::setup();
+            Pwm::<TIM, C2>::setup();
+        }
+
+        fn channels() -> Self::Channels {
+            (
+                Pwm {
+                    _channel: PhantomData,
+                    _tim: PhantomData,
+                },
+                Pwm {
+                    _channel: PhantomData,
+                    _tim: PhantomData,
+                },
+            )
+        }
+    }
+
+    impl<TIM, P1, P2, P3> Pins<TIM> for (P1, P2, P3)
+    where
+        P1: PinC1<TIM>,
+        P2: PinC2<TIM>,
+        P3: PinC3<TIM>,
+        Pwm<TIM, C1>: Configure,
+        Pwm<TIM, C2>: Configure,
+        Pwm<TIM, C3>: Configure,
+    {
+        type Channels = (Pwm<TIM, C1>, Pwm<TIM, C2>, Pwm<TIM, C3>);
+
+        fn configure() {
+            Pwm::<TIM, C1>::setup();
+            Pwm::<TIM, C2>::setup();
+            Pwm::<TIM, C3>::setup();
+        }
+
+        fn channels() -> Self::Channels {
+            (
+                Pwm {
+                    _channel: PhantomData,
+                    _tim: PhantomData,
+                },
+                Pwm {
+                    _channel: PhantomData,
+                    _tim: PhantomData,
+                },
+                Pwm {
+                    _channel: PhantomData,
+                    _tim: PhantomData,
+                },
+            )
+        }
+    }
+
+    impl<TIM, P1, P2, P3, P4> Pins<TIM> for (P1, P2, P3, P4)
+    where
+        P1: PinC1<TIM>,
+        P2: PinC2<TIM>,
+        P3: PinC3<TIM>,
+        P4: PinC4<TIM>,
+        Pwm<TIM, C1>: Configure,
+        Pwm<TIM, C2>: Configure,
+        Pwm<TIM, C3>: Configure,
+        Pwm<TIM, C4>: Configure,
+    {
+        type Channels = (Pwm<TIM, C1>, Pwm<TIM, C2>, Pwm<TIM, C3>, Pwm<TIM, C4>);
+
+        fn configure() {
+            Pwm::<TIM, C1>::setup();
+            Pwm::<TIM, C2>::setup();
+            Pwm::<TIM, C3>::setup();
+            Pwm::<TIM, C4>::setup();
+        }
+
+        fn channels() -> Self::Channels {
+            (
+                Pwm {
+                    _channel: PhantomData,
+                    _tim: PhantomData,
+                },
+                Pwm {
+                    _channel: PhantomData,
+                    _tim: PhantomData,
+                },
+                Pwm {
+                    _channel: PhantomData,
+                    _tim: PhantomData,
+                },
+                Pwm {
+                    _channel: PhantomData,
+                    _tim: PhantomData,
+                },
+            )
+        }
+    }
+
+    macro_rules! pwm_channel {
+        ($($TIM:ident: ($CH:ident, $ccmr_out:ident, $ocxpe:ident, $ocxm:ident, $ccxe:ident, $ccr:ident),)+) => {
+            $(
+                impl Configure for Pwm<$TIM, $CH> {
+                    fn setup() {
+                        let tim = unsafe { &*<$TIM>::ptr() };
+                        // PWM mode 1 with preload enabled
+                        tim.$ccmr_out
+                            .modify(|_, w| unsafe { w.$ocxm().bits(0b110).$ocxpe().set_bit() });
+                    }
+                }
+
+                impl PwmPin for Pwm<$TIM, $CH> {
+                    type Duty = u16;
+
+                    fn disable(&mut self) {
+                        let tim = unsafe { &*<$TIM>::ptr() };
+                        tim.ccer.modify(|_, w| w.$ccxe().clear_bit());
+                    }
+
+                    fn enable(&mut self) {
+                        let tim = unsafe { &*<$TIM>::ptr() };
+                        tim.ccer.modify(|_, w| w.$ccxe().set_bit());
+                    }
+
+                    fn get_duty(&self) -> u16 {
+                        let tim = unsafe { &*<$TIM>::ptr() };
+                        u16(tim.$ccr.read().bits()).unwrap()
+                    }
+
+                    fn get_max_duty(&self) -> u16 {
+                        let tim = unsafe { &*<$TIM>::ptr() };
+                        u16(tim.arr.read().bits()).unwrap()
+                    }
+
+                    fn set_duty(&mut self, duty: u16) {
+                        let tim = unsafe { &*<$TIM>::ptr() };
+                        tim.$ccr.write(|w| unsafe { w.bits(u32(duty)) });
+                    }
+                }
+            )+
+        };
+    }
+
+    pwm_channel!(
+        TIM1: (C1, ccmr1_output, oc1pe, oc1m, cc1e, ccr1),
+        TIM1: (C2, ccmr1_output, oc2pe, oc2m, cc2e, ccr2),
+        TIM1: (C3, ccmr2_output, oc3pe, oc3m, cc3e, ccr3),
+        TIM1: (C4, ccmr2_output, oc4pe, oc4m, cc4e, ccr4),
+
+        TIM2: (C1, ccmr1_output, oc1pe, oc1m, cc1e, ccr1),
+        TIM2: (C2, ccmr1_output, oc2pe, oc2m, cc2e, ccr2),
+        TIM2: (C3, ccmr2_output, oc3pe, oc3m, cc3e, ccr3),
+        TIM2: (C4, ccmr2_output, oc4pe, oc4m, cc4e, ccr4),
+
+        TIM3: (C1, ccmr1_output, oc1pe, oc1m, cc1e, ccr1),
+        TIM3: (C2, ccmr1_output, oc2pe, oc2m, cc2e, ccr2),
+        TIM3: (C3, ccmr2_output, oc3pe, oc3m, cc3e, ccr3),
+        TIM3: (C4, ccmr2_output, oc4pe, oc4m, cc4e, ccr4),
+
+        TIM4: (C1, ccmr1_output, oc1pe, oc1m, cc1e, ccr1),
+        TIM4: (C2, ccmr1_output, oc2pe, oc2m, cc2e, ccr2),
+        TIM4: (C3, ccmr2_output, oc3pe, oc3m, cc3e, ccr3),
+        TIM4: (C4, ccmr2_output, oc4pe, oc4m, cc4e, ccr4),
+
+        TIM5: (C1, ccmr1_output, oc1pe, oc1m, cc1e, ccr1),
+        TIM5: (C2, ccmr1_output, oc2pe, oc2m, cc2e, ccr2),
+        TIM5: (C3, ccmr2_output, oc3pe, oc3m, cc3e, ccr3),
+        TIM5: (C4, ccmr2_output, oc4pe, oc4m, cc4e, ccr4),
+
+        TIM8: (C1, ccmr1_output, oc1pe, oc1m, cc1e, ccr1),
+        TIM8: (C2, ccmr1_output, oc2pe, oc2m, cc2e, ccr2),
+        TIM8: (C3, ccmr2_output, oc3pe, oc3m, cc3e, ccr3),
+        TIM8: (C4, ccmr2_output, oc4pe, oc4m, cc4e, ccr4),
+
+        TIM9: (C1, ccmr1_output, oc1pe, oc1m, cc1e, ccr1),
+        TIM9: (C2, ccmr1_output, oc2pe, oc2m, cc2e, ccr2),
+
+        TIM10: (C1, ccmr1_output, oc1pe, oc1m, cc1e, ccr1),
+
+        TIM11: (C1, ccmr1_output, oc1pe, oc1m, cc1e, ccr1),
+    );
+
+    macro_rules! pwm_timer {
+        ($($TIM:ident: ($timXen:ident, $timXrst:ident, $apb:ident, $timclk:ident $(, $moe:ident)?),)+) => {
+            $(
+                impl Timer<$TIM> {
+                    /// Configures a TIM peripheral to generate PWM signals on `pins`
+                    pub fn pwm<PINS, T>(
+                        tim: $TIM,
+                        pins: PINS,
+                        freq: T,
+                        clocks: Clocks,
+                        apb: &mut $apb,
+                    ) -> PINS::Channels
+                    where
+                        PINS: Pins<$TIM>,
+                        T: Into<Hertz>,
+                    {
+                        apb.enr().modify(|_, w| w.$timXen().set_bit());
+                        apb.rstr().modify(|_, w| w.$timXrst().set_bit());
+                        apb.rstr().modify(|_, w| w.$timXrst().clear_bit());
+
+                        PINS::configure();
+
+                        let clock = clocks.$timclk();
+                        let freq = freq.into().0;
+                        let ticks = clock.0 / freq;
+                        let psc = u16((ticks - 1) / (1 << 16)).unwrap();
+                        tim.psc.write(|w| unsafe { w.psc().bits(psc) });
+
+                        let arr = u16(ticks / u32(psc + 1)).unwrap();
+                        tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
+
+                        // Trigger an update event to load the prescaler value
+                        tim.egr.write(|w| w.ug().set_bit());
+                        tim.sr.modify(|_, w| w.uif().clear_bit());
+
+                        $(tim.bdtr.modify(|_, w| w.$moe().set_bit());)?
+
+                        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+                        PINS::channels()
+                    }
+                }
+            )+
+        };
+    }
+
+    pwm_timer!(
+        TIM1: (tim1en, tim1rst, APB2, timclk2, moe),
+        TIM2: (tim2en, tim2rst, APB1, timclk1),
+        TIM3: (tim3en, tim3rst, APB1, timclk1),
+        TIM4: (tim4en, tim4rst, APB1, timclk1),
+        TIM5: (tim5en, tim5rst, APB1, timclk1),
+        TIM8: (tim8en, tim8rst, APB2, timclk2, moe),
+        TIM9: (tim9en, tim9rst, APB2, timclk2),
+        TIM10: (tim10en, tim10rst, APB2, timclk2),
+        TIM11: (tim11en, tim11rst, APB2, timclk2),
+    );
+}
+
+/// Quadrature Encoder Interface (QEI) mode
+pub mod qei {
+    use crate::hal::{self, Direction};
+    use crate::pac::{TIM1, TIM2, TIM3, TIM4, TIM5};
+    use crate::rcc::{APB1, APB2};
+
+    use super::{PinC1, PinC2};
+
+    /// A quadrature encoder interface built from a timer and a pair of pins
+    pub struct Qei<TIM, PINS> {
+        tim: TIM,
+        pins: PINS,
+    }
+
+    macro_rules! qei_hal {
+        ($($TIM:ident: ($tim:ident, $timXen:ident, $timXrst:ident, $apb:ident, $arr:expr),)+) => {
+            $(
+                impl<P1, P2> Qei<$TIM, (P1, P2)>
+                where
+                    P1: PinC1<$TIM>,
+                    P2: PinC2<$TIM>,
+                {
+                    /// Configures a TIM peripheral as a quadrature encoder interface
+                    pub fn $tim(tim: $TIM, pins: (P1, P2), apb: &mut $apb) -> Self {
+                        apb.enr().modify(|_, w| w.$timXen().set_bit());
+                        apb.rstr().modify(|_, w| w.$timXrst().set_bit());
+                        apb.rstr().modify(|_, w| w.$timXrst().clear_bit());
+
+                        // Configure TI1 and TI2 as input captures, mapped to TI1/TI2 respectively
+                        tim.ccmr1_input()
+                            .write(|w| unsafe { w.cc1s().bits(0b01).cc2s().bits(0b01) });
+
+                        // Count on both TI1 and TI2 edges
+                        tim.smcr.write(|w| unsafe { w.sms().bits(0b011) });
+
+                        tim.arr.write(|w| unsafe { w.bits($arr) });
+
+                        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+                        Qei { tim, pins }
+                    }
+
+                    /// Releases the TIM peripheral and pins
+                    pub fn release(self) -> ($TIM, (P1, P2)) {
+                        (self.tim, self.pins)
+                    }
+                }
+
+                impl<P1, P2> hal::Qei for Qei<$TIM, (P1, P2)> {
+                    type Count = u32;
+
+                    fn count(&self) -> u32 {
+                        self.tim.cnt.read().bits()
+                    }
+
+                    fn direction(&self) -> Direction {
+                        if self.tim.cr1.read().dir().is_up() {
+                            Direction::Upcounting
+                        } else {
+                            Direction::Downcounting
+                        }
+                    }
+                }
+            )+
+        };
+    }
+
+    qei_hal!(
+        TIM1: (tim1, tim1en, tim1rst, APB2, 0xFFFF),
+        TIM2: (tim2, tim2en, tim2rst, APB1, 0xFFFF_FFFF),
+        TIM3: (tim3, tim3en, tim3rst, APB1, 0xFFFF),
+        TIM4: (tim4, tim4en, tim4rst, APB1, 0xFFFF),
+        TIM5: (tim5, tim5en, tim5rst, APB1, 0xFFFF_FFFF),
+    );
+}
+
+/// RTIC monotonic timer implementation
+#[cfg(feature = "rtic-monotonic")]
+pub mod monotonic {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use cast::u16;
+    use rtic_monotonic::Monotonic;
+
+    use crate::pac::{TIM2, TIM5};
+    use crate::rcc::{Clocks, APB1};
+
+    /// A monotonic, free-running timer suitable for use with RTIC
+    ///
+    /// `CLOCK_HZ` is the tick rate `MonoTimer` is configured to run at; the
+    /// 32-bit hardware counter is extended to a full 64-bit instant by
+    /// counting overflows of the update event.
+    pub struct MonoTimer<TIM, const CLOCK_HZ: u32> {
+        tim: TIM,
+        overflow: AtomicU32,
+    }
+
+    macro_rules! mono_hal {
+        ($($TIM:ident: ($tim:ident, $timXen:ident, $timXrst:ident, $timclk:ident),)+) => {
+            $(
+                impl<const CLOCK_HZ: u32> MonoTimer<$TIM, CLOCK_HZ> {
+                    /// Configures `tim` as a free-running up-counter ticking at `CLOCK_HZ`
+                    pub fn $tim(tim: $TIM, clocks: Clocks, apb: &mut APB1) -> Self {
+                        apb.enr().modify(|_, w| w.$timXen().set_bit());
+                        apb.rstr().modify(|_, w| w.$timXrst().set_bit());
+                        apb.rstr().modify(|_, w| w.$timXrst().clear_bit());
+
+                        let clock = clocks.$timclk();
+                        let psc = u16(clock.0 / CLOCK_HZ - 1).unwrap();
+                        tim.psc.write(|w| unsafe { w.psc().bits(psc) });
+                        tim.arr.write(|w| unsafe { w.bits(0xFFFF_FFFF) });
+
+                        // Trigger an update event to load the prescaler value,
+                        // then clear the spurious flag it raises, before
+                        // enabling the update interrupt: otherwise, if this
+                        // timer's NVIC line is already unmasked, the forced
+                        // update is taken as a real interrupt and overflow
+                        // gets bumped once for free
+                        tim.egr.write(|w| w.ug().set_bit());
+                        tim.sr.modify(|_, w| w.uif().clear_bit());
+
+                        // Fire the update interrupt on overflow so we can extend
+                        // the 32-bit counter into a 64-bit instant
+                        tim.dier.write(|w| w.uie().set_bit());
+
+                        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+                        MonoTimer {
+                            tim,
+                            overflow: AtomicU32::new(0),
+                        }
+                    }
+                }
+
+                impl<const CLOCK_HZ: u32> Monotonic for MonoTimer<$TIM, CLOCK_HZ> {
+                    type Instant = fugit::TimerInstantU64<CLOCK_HZ>;
+                    type Duration = fugit::TimerDurationU64<CLOCK_HZ>;
+
+                    const DISABLE_INTERRUPT_ON_EMPTY_QUEUE: bool = false;
+
+                    fn now(&mut self) -> Self::Instant {
+                        // `overflow` can be bumped by the update interrupt in
+                        // between the two reads below, right as `CNT` wraps
+                        // from near `0xFFFF_FFFF` back to a small value; a
+                        // stale `overflow` paired with the post-wrap `CNT`
+                        // would read as a large jump backwards in time, so
+                        // re-read `overflow` and retry if it changed
+                        loop {
+                            let before = self.overflow.load(Ordering::Relaxed);
+                            let low = self.tim.cnt.read().bits() as u64;
+                            let after = self.overflow.load(Ordering::Relaxed);
+
+                            if before == after {
+                                return Self::Instant::from_ticks(((after as u64) << 32) | low);
+                            }
+                        }
+                    }
+
+                    fn zero() -> Self::Instant {
+                        Self::Instant::from_ticks(0)
+                    }
+
+                    unsafe fn reset(&mut self) {
+                        self.overflow.store(0, Ordering::Relaxed);
+                        self.tim.cnt.reset();
+                    }
+
+                    fn set_compare(&mut self, instant: Self::Instant) {
+                        self.tim
+                            .ccr1
+                            .write(|w| unsafe { w.bits(instant.ticks() as u32) });
+                    }
+
+                    fn clear_compare_flag(&mut self) {
+                        self.tim.sr.modify(|_, w| w.cc1if().clear_bit());
+                    }
+
+                    fn enable_timer(&mut self) {
+                        self.tim.dier.modify(|_, w| w.cc1ie().set_bit());
+                    }
+
+                    fn disable_timer(&mut self) {
+                        self.tim.dier.modify(|_, w| w.cc1ie().clear_bit());
+                    }
+                }
+
+                impl<const CLOCK_HZ: u32> MonoTimer<$TIM, CLOCK_HZ> {
+                    /// Services the update interrupt, extending the 32-bit
+                    /// hardware counter into a 64-bit monotonic instant
+                    pub fn on_interrupt(&mut self) {
+                        if self.tim.sr.read().uif().bit_is_set() {
+                            self.tim.sr.modify(|_, w| w.uif().clear_bit());
+                            self.overflow.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+            )+
+        };
+    }
+
+    mono_hal!(
+        TIM2: (tim2, tim2en, tim2rst, timclk1),
+        TIM5: (tim5, tim5en, tim5rst, timclk1),
+    );
+}